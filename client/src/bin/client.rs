@@ -15,7 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     xfl::config::init_config(&opt).await;
 
     // 传递语言类型，然后选择不同的引擎并执行
-    xfl::schedule::select_engine(&opt).await;
+    xfl::schedule::select_engine(&opt).await?;
 
     Ok(())
 }
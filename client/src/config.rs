@@ -1,27 +1,103 @@
-use crate::parse::CommandLine;
+use crate::parse::{parse_address, CommandLine, ServerAddress};
+use anyhow::{Context, Result};
+use config::{Config, Environment, File};
+use serde::{Deserialize, Deserializer};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-// use std::path::PathBuf;
 use tokio::sync::OnceCell;
 
+/// 运行环境，决定叠加哪个 `settings/<env>.toml` 覆盖层，默认 development
+const ENV_VAR: &str = "XFL_ENV";
+const DEFAULT_ENV: &str = "development";
 
 // 结构体，包括输出目录、服务端地址
+#[derive(Debug, Deserialize)]
 pub struct ServerConfig {
-    pub server_addr: SocketAddr,
+    #[serde(default = "default_server_addr", deserialize_with = "deserialize_server_addr")]
+    pub server_addr: ServerAddress,
+    /// 默认 `C`，与 `LibFuzzerEngine` 是目前唯一已注册的后端保持一致，这样不传
+    /// `-l/-e` 也能直接跑起来
+    #[serde(default = "default_language")]
     pub language: String,
+    #[serde(default = "default_engine")]
     pub engine: String,
+    #[serde(default)]
     pub persistent: u8,
+    #[serde(default)]
     pub args: String,
+
+    /// 语料库目录，worker 之间共享/同步的测试用例存放处
+    #[serde(default = "default_corpus_dir")]
+    pub corpus_dir: String,
+    /// fuzzer 输出目录（crash、日志等）
+    #[serde(default = "default_output_dir")]
+    pub output_dir: String,
+    /// 并行 worker 数量
+    #[serde(default = "default_worker_count")]
+    pub worker_count: usize,
+    /// 单次 fuzzer 调用的超时时间（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 向调度端上报 RuntimeStats 的间隔（秒）
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: u64,
+}
+
+fn default_server_addr() -> ServerAddress {
+    ServerAddress::Tcp(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051))
+}
+
+/// `server_addr` 在配置文件/环境变量里以字符串形式出现（如 `"127.0.0.1:50051"` 或
+/// `"unix:@xfl-scheduler"`），复用命令行解析的同一套语法
+fn deserialize_server_addr<'de, D>(deserializer: D) -> Result<ServerAddress, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    parse_address(&raw).map_err(serde::de::Error::custom)
+}
+
+fn default_language() -> String {
+    "C".to_string()
+}
+
+fn default_engine() -> String {
+    "xlibfuzzer".to_string()
+}
+
+fn default_corpus_dir() -> String {
+    "corpus".to_string()
+}
+
+fn default_output_dir() -> String {
+    "output".to_string()
+}
+
+fn default_worker_count() -> usize {
+    1
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+fn default_report_interval_secs() -> u64 {
+    1
 }
 
 // 给定默认ip地址和端口
 impl Default for ServerConfig {
     fn default() -> Self {
         ServerConfig {
-            server_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051),
-            language: Default::default(),
-            engine: Default::default(),
+            server_addr: default_server_addr(),
+            language: default_language(),
+            engine: default_engine(),
             persistent: Default::default(),
             args: Default::default(),
+            corpus_dir: default_corpus_dir(),
+            output_dir: default_output_dir(),
+            worker_count: default_worker_count(),
+            timeout_secs: default_timeout_secs(),
+            report_interval_secs: default_report_interval_secs(),
         }
     }
 }
@@ -30,17 +106,58 @@ impl Default for ServerConfig {
 pub static SERVER_CONFIG: OnceCell<ServerConfig> = OnceCell::const_new();
 
 /// 异步函数，使用命令行参数初始化配置项
+///
+/// 配置分层叠加，后面的层覆盖前面的层：
+/// `settings/default.toml` -> `settings/<XFL_ENV>.toml` -> 进程环境变量(`XFL_*`) -> 命令行参数
 pub async fn init_config(opt: &CommandLine) {
     // 从get_or_init获取值，如果尚未初始化，则执行闭包中的逻辑进行初始化，从而确保在并发环境下只初始化一次
     SERVER_CONFIG
         .get_or_init(|| async {
-            // 创建实例
-            ServerConfig {
-                server_addr: opt.address,
-                language: opt.language.clone(),
-                engine: opt.engine.clone(),
-                persistent: opt.persistent,
-                args: opt.args.clone(),
-            }
-        }).await;
-}
\ No newline at end of file
+            load_layered_config(opt).unwrap_or_else(|err| {
+                log::warn!("failed to load layered config, falling back to CLI-only: {err:#}");
+                let mut config = ServerConfig::default();
+                apply_cli_overrides(&mut config, opt);
+                config
+            })
+        })
+        .await;
+}
+
+/// 按 default -> 环境覆盖 -> 进程环境变量 -> CLI 的顺序叠加配置层
+fn load_layered_config(opt: &CommandLine) -> Result<ServerConfig> {
+    let env = std::env::var(ENV_VAR).unwrap_or_else(|_| DEFAULT_ENV.to_string());
+
+    let mut config: ServerConfig = Config::builder()
+        .add_source(File::with_name("settings/default").required(false))
+        .add_source(File::with_name(&format!("settings/{env}")).required(false))
+        .add_source(Environment::with_prefix("XFL").separator("__"))
+        .build()
+        .with_context(|| "failed to build layered config")?
+        .try_deserialize()
+        .with_context(|| "failed to deserialize ServerConfig")?;
+
+    // CLI flags are the final, highest-priority layer, but only for the flags the
+    // user actually passed — otherwise they'd stomp on whatever settings/*.toml set.
+    apply_cli_overrides(&mut config, opt);
+
+    Ok(config)
+}
+
+/// 只把命令行里用户显式传入的字段叠加到 `config` 上，留空的 flag 不覆盖已有的配置层
+fn apply_cli_overrides(config: &mut ServerConfig, opt: &CommandLine) {
+    if let Some(address) = &opt.address {
+        config.server_addr = address.clone();
+    }
+    if let Some(language) = &opt.language {
+        config.language = language.clone();
+    }
+    if let Some(engine) = &opt.engine {
+        config.engine = engine.clone();
+    }
+    if opt.persistent > 0 {
+        config.persistent = opt.persistent;
+    }
+    if let Some(args) = &opt.args {
+        config.args = args.clone();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::engine::libfuzzer_c::RuntimeStats;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 一个 worker 的崩溃记录：按内容去重，重复的 crash 只计数、不重复落盘
+pub struct CrashStore {
+    dir: PathBuf,
+    index_path: PathBuf,
+    manifest_path: PathBuf,
+    /// 内容签名 -> 出现次数
+    signature_counts: HashMap<String, u64>,
+    manifest: RunManifest,
+}
+
+/// 运行元数据，以人类可读的 JSON 写在 `manifest.json`；二进制的 `index.bin` 只保存签名计数
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunManifest {
+    run_id: String,
+    cmd: String,
+    started_at: u64,
+    final_stats: Option<RuntimeStats>,
+    /// 目前已知的所有唯一 crash 签名
+    crash_signatures: Vec<String>,
+}
+
+/// 一次 crash 落盘/去重之后的结果
+pub struct CrashRecord {
+    pub signature: String,
+    pub occurrences: u64,
+    pub is_new: bool,
+}
+
+impl CrashStore {
+    /// 为某个 worker 打开（或在不存在时创建）它的 crash 记录目录。
+    ///
+    /// 目录以 `worker_id` 命名而不是 `run_id`：每次启动 client 时 worker 都会生成新的
+    /// `run_id`，按 `worker_id` 命名才能重新加载并继续累加同一份签名计数和 manifest。
+    pub fn load_or_create(worker_id: usize, run_id: &str, cmd: &str, output_dir: &Path) -> Result<Self> {
+        let dir = output_dir.join("crashes").join(format!("worker-{worker_id}"));
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create crash dir {dir:?}"))?;
+
+        let index_path = dir.join("index.bin");
+        let manifest_path = dir.join("manifest.json");
+
+        let signature_counts: HashMap<String, u64> = if index_path.exists() {
+            let bytes = std::fs::read(&index_path)
+                .with_context(|| format!("failed to read crash index {index_path:?}"))?;
+            bincode::deserialize(&bytes).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
+        let manifest = if manifest_path.exists() {
+            let text = std::fs::read_to_string(&manifest_path)
+                .with_context(|| format!("failed to read crash manifest {manifest_path:?}"))?;
+            serde_json::from_str(&text).unwrap_or_else(|_| RunManifest::default())
+        } else {
+            RunManifest::default()
+        };
+
+        let mut store = CrashStore {
+            dir,
+            index_path,
+            manifest_path,
+            signature_counts,
+            manifest,
+        };
+        store.manifest.run_id = run_id.to_string();
+        store.manifest.cmd = cmd.to_string();
+        if store.manifest.started_at == 0 {
+            store.manifest.started_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+        }
+
+        Ok(store)
+    }
+
+    /// 把一个 crash artifact 纳入管理：按内容 hash 去重，新签名才真正拷贝一份、
+    /// 重复的签名只是把计数加一
+    pub fn record(&mut self, artifact: &Path) -> Result<CrashRecord> {
+        let bytes = std::fs::read(artifact)
+            .with_context(|| format!("failed to read crash artifact {artifact:?}"))?;
+        let signature = content_signature(&bytes);
+
+        let occurrences = {
+            let count = self.signature_counts.entry(signature.clone()).or_insert(0);
+            *count += 1;
+            *count
+        };
+        let is_new = occurrences == 1;
+
+        if is_new {
+            let dest = self.dir.join(&signature);
+            std::fs::copy(artifact, &dest)
+                .with_context(|| format!("failed to copy crash artifact to {dest:?}"))?;
+            self.manifest.crash_signatures.push(signature.clone());
+        }
+
+        self.persist()?;
+
+        Ok(CrashRecord {
+            signature,
+            occurrences,
+            is_new,
+        })
+    }
+
+    /// 更新 manifest 里的最终 `RuntimeStats` 快照并落盘
+    pub fn update_stats(&mut self, stats: &RuntimeStats) -> Result<()> {
+        self.manifest.final_stats = Some(stats.clone());
+        self.persist()
+    }
+
+    fn persist(&self) -> Result<()> {
+        let index_bytes = bincode::serialize(&self.signature_counts)
+            .with_context(|| "failed to serialize crash index")?;
+        std::fs::write(&self.index_path, index_bytes)
+            .with_context(|| format!("failed to write crash index {:?}", self.index_path))?;
+
+        let manifest_json = serde_json::to_string_pretty(&self.manifest)
+            .with_context(|| "failed to serialize crash manifest")?;
+        std::fs::write(&self.manifest_path, manifest_json)
+            .with_context(|| format!("failed to write crash manifest {:?}", self.manifest_path))?;
+
+        Ok(())
+    }
+}
+
+/// 简单的内容签名：不追求密码学强度，只用来判断两个 crash 输入是否字节相同
+fn content_signature(bytes: &[u8]) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
@@ -1,5 +1,7 @@
 use crate::parse::CommandLine;
 use crate::config::SERVER_CONFIG;
+use crate::engine::FuzzEngine;
+use crate::crash::CrashStore;
 
 use anyhow::{anyhow, Context, Result, bail, format_err};
 use log::{info, warn, error, debug};
@@ -24,8 +26,9 @@ use std::{
 use tempfile::{tempdir_in, TempDir};
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
-    sync::{mpsc, Notify},
-    time::{sleep, Duration, Instant},
+    signal,
+    sync::{mpsc, watch},
+    time::{sleep, timeout, Duration, Instant},
 };
 use rand::thread_rng;
 use tempfile::tempdir;
@@ -35,34 +38,30 @@ use serde::Serialize;
 // use std::process::Command;
 
 
+/// c/c++ 语言的 libFuzzer 引擎
+pub struct LibFuzzerEngine;
+
+#[async_trait]
+impl FuzzEngine for LibFuzzerEngine {
+    async fn run_fuzzer(&self, opt: &CommandLine) -> Result<()> {
+        run_fuzzer(opt).await
+    }
+
+    fn supports(language: &str, engine: &str) -> bool {
+        language.eq_ignore_ascii_case("c") && engine == "xlibfuzzer"
+    }
+}
+
 /// c/c++语言的libfuzzer
-pub async fn run_fuzzer(opt: &CommandLine){
+async fn run_fuzzer(opt: &CommandLine) -> Result<()> {
     println!("opt: {:?}", opt);
-    println!("Scheduler service listening on {}", SERVER_CONFIG.get().unwrap().server_addr);
-
-    let runtime_stats = RuntimeStats {
-        worker_id: 0,
-        run_id: Uuid::new_v4(),
-        count: 0,
-        execs_sec: 0.0,
-        cmd: SERVER_CONFIG.get().unwrap().args.clone(),
-
-        crashes: 0,
-        queuees: 0,
-
-        basicblocks: 0,
-        whole_basicblocks: 0,
-        functions: 0,
-        whole_functions: 0,
-        lines: 0,
-        whole_lines: 0,
-        edges: 0,
-        whole_edges: 0,
-    };
-    let my_instance = LibFuzzerFuzzTask::new(runtime_stats);
-    my_instance.run().await;
+    println!("Reporting stats to scheduler at {}", SERVER_CONFIG.get().unwrap().server_addr);
+
+    let my_instance = LibFuzzerFuzzTask::new(SERVER_CONFIG.get().unwrap().args.clone());
+    my_instance.run().await
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RuntimeStats {
     // 开始时间， 执行次数、崩溃数量、队列数量、覆盖率（基本块、函数、行、边）
     worker_id: usize,
@@ -85,59 +84,445 @@ pub struct RuntimeStats {
     whole_edges: u64,
 }
 
+impl RuntimeStats {
+    /// 转换成上报给调度端的 protobuf 消息
+    pub(crate) fn to_report(&self) -> crate::grpc::RuntimeStatsReport {
+        crate::grpc::RuntimeStatsReport {
+            worker_id: self.worker_id as u64,
+            run_id: self.run_id.to_string(),
+            count: self.count,
+            execs_sec: self.execs_sec,
+            cmd: self.cmd.clone(),
+            crashes: self.crashes,
+            queuees: self.queuees,
+            basicblocks: self.basicblocks,
+            whole_basicblocks: self.whole_basicblocks,
+            functions: self.functions,
+            whole_functions: self.whole_functions,
+            lines: self.lines,
+            whole_lines: self.whole_lines,
+            edges: self.edges,
+            whole_edges: self.whole_edges,
+        }
+    }
+}
+
+/// 单次 fuzzer 运行的结局，用于决定是否、以及如何重启
+#[derive(Debug)]
+enum FuzzOutcome {
+    /// 进程正常退出（用户按了 Ctrl+C 交给 libFuzzer 处理之类）
+    Clean,
+    /// 进程崩溃或被信号杀死，`artifact` 是在语料目录里找到的 crash 产物路径（如果找到的话）
+    Crash { status: ExitStatus, artifact: Option<PathBuf> },
+    /// 跑满了 `timeout_secs` 被我们自己杀掉的，不是 crash：不计入 `crashes`，
+    /// 也不走 crash-artifact 查找/去重（libFuzzer 自己的单用例超时才会留下
+    /// `timeout-*` artifact，那种情况走的是上面的 `Crash` 分支）
+    TimedOut,
+}
+
+/// 重启回退的初始等待时间，随后按指数退避增长
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_millis(500);
+/// 重启回退等待时间上限，避免长时间卡死
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
 pub struct LibFuzzerFuzzTask {
-    runtime_stats: RuntimeStats,
+    cmd_template: String,
+    // `watch` 保存的是最新的值而不是一次性的唤醒：worker 即便没有正好 await 在
+    // `wait_for_shutdown` 上（比如正在处理上一轮的 FuzzOutcome、记录 crash、睡 backoff），
+    // 下次检查时 `*shutdown_rx.borrow()` 也能读到 `true`，不会错过停止信号。
+    shutdown_tx: watch::Sender<bool>,
 }
 
 impl LibFuzzerFuzzTask {
-    /// 创建新的 LibFuzzerFuzzTask 实例
-    pub fn new(runtime_stats: RuntimeStats,) -> Self {
+    /// 创建新的 LibFuzzerFuzzTask 实例，`cmd` 是每个 worker 都会执行的 fuzzer 命令
+    pub fn new(cmd: String) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
         LibFuzzerFuzzTask {
-            // 初始化字段
-            runtime_stats,
+            cmd_template: cmd,
+            shutdown_tx,
         }
     }
 
     /// 运行 fuzzer 任务
     pub async fn run(&self) -> Result<()> {
+        let shutdown_tx = self.shutdown_tx.clone();
+        tokio::spawn(async move {
+            if signal::ctrl_c().await.is_ok() {
+                info!("received shutdown signal, stopping workers after their current run");
+                let _ = shutdown_tx.send(true);
+            }
+        });
+
         self.run_fuzzers().await
     }
 
-    /// 开启 fuzzer 进程
+    /// 启动 `worker_count` 个并行 fuzzer 进程，每个进程拥有独立的语料目录，
+    /// 并周期性地在各 worker 目录之间同步新发现的语料
     async fn run_fuzzers(&self) -> Result<()> {
-        let worker_id = 1;
-        self.start_fuzzer_monitor(worker_id).await?;
+        let worker_count = SERVER_CONFIG.get().unwrap().worker_count.max(1);
+        let corpus_root = PathBuf::from(&SERVER_CONFIG.get().unwrap().corpus_dir);
+        std::fs::create_dir_all(&corpus_root)
+            .with_context(|| format!("failed to create corpus dir {corpus_root:?}"))?;
+
+        let worker_stats: Vec<Arc<std::sync::Mutex<RuntimeStats>>> = (1..=worker_count)
+            .map(|worker_id| {
+                Arc::new(std::sync::Mutex::new(RuntimeStats {
+                    worker_id,
+                    run_id: Uuid::new_v4(),
+                    cmd: self.cmd_template.clone(),
+                    ..Default::default()
+                }))
+            })
+            .collect();
+
+        let sync_task = tokio::spawn(sync_corpora(corpus_root.clone(), self.shutdown_tx.subscribe()));
+
+        let workers = worker_stats.iter().cloned().map(|stats| {
+            let corpus_root = corpus_root.clone();
+            let shutdown = self.shutdown_tx.subscribe();
+            async move {
+                let work_dir = tempdir_in(&corpus_root).with_context(|| {
+                    format!("failed to create work dir for worker {}", stats.lock().unwrap().worker_id)
+                })?;
+                start_fuzzer_monitor(stats, shutdown, work_dir.path()).await
+            }
+        });
+
+        try_join_all(workers).await?;
+        sync_task.abort();
+
+        // 这里只是打日志，不会再上报给调度端：每个 worker 在 `run_fuzzer` 里已经通过自己的
+        // `report::run` 任务把 `RuntimeStats` 实时上报过去了，调度端按 `worker_id` 收齐后自己
+        // 求和即可得到和这里一样的合并视图。`aggregate_stats` 存在是为了让这条落地日志能看到
+        // "这一批 worker 总共跑出了什么"，不是一条额外的上报。
+        let combined = aggregate_stats(&worker_stats);
+        info!("all {worker_count} worker(s) finished, aggregated stats (log-only, scheduler sums per-worker reports itself): {combined:?}");
+
         Ok(())
     }
+}
 
-    /// 持续运行 fuzzer 进程
-    async fn start_fuzzer_monitor(&self, worker_id: usize) -> Result<()> {
-        // loop {
-        //     self.run_fuzzer(worker_id).await?;
-        // }
-        self.run_fuzzer(worker_id).await?;
-        Ok(())
+/// 持续运行单个 worker 的 fuzzer 进程：`-p/--persistent` 为 0 时只跑一次，否则在进程退出后
+/// （无论正常退出还是崩溃）按指数退避重启，直至收到 shutdown 通知
+async fn start_fuzzer_monitor(
+    stats: Arc<std::sync::Mutex<RuntimeStats>>,
+    mut shutdown: watch::Receiver<bool>,
+    work_dir: &Path,
+) -> Result<()> {
+    let worker_id = stats.lock().unwrap().worker_id;
+    let persistent = SERVER_CONFIG.get().unwrap().persistent;
+    let output_dir = PathBuf::from(&SERVER_CONFIG.get().unwrap().output_dir);
+    let mut backoff = INITIAL_RESTART_BACKOFF;
+
+    let mut crash_store = CrashStore::load_or_create(
+        worker_id,
+        &stats.lock().unwrap().run_id.to_string(),
+        &stats.lock().unwrap().cmd,
+        &output_dir,
+    )
+    .with_context(|| format!("failed to open crash store for worker {worker_id}"))?;
+
+    loop {
+        if *shutdown.borrow() {
+            info!("worker {worker_id} shutting down, flushing final stats");
+            break;
+        }
+
+        let outcome = tokio::select! {
+            _ = wait_for_shutdown(&mut shutdown) => {
+                info!("worker {worker_id} shutting down, flushing final stats");
+                break;
+            }
+            outcome = run_fuzzer(stats.clone(), work_dir) => outcome?,
+        };
+
+        match outcome {
+            FuzzOutcome::Clean => {
+                backoff = INITIAL_RESTART_BACKOFF;
+            }
+            FuzzOutcome::TimedOut => {
+                backoff = INITIAL_RESTART_BACKOFF;
+            }
+            FuzzOutcome::Crash { status, artifact } => {
+                stats.lock().unwrap().crashes += 1;
+                match artifact {
+                    Some(path) => match crash_store.record(&path) {
+                        Ok(record) if record.is_new => {
+                            warn!("worker {worker_id} crashed ({status:?}), new crash signature {} saved", record.signature);
+                            let _ = std::fs::remove_file(&path);
+                        }
+                        Ok(record) => {
+                            warn!(
+                                "worker {worker_id} crashed ({status:?}), duplicate of signature {} (seen {} times)",
+                                record.signature, record.occurrences
+                            );
+                            let _ = std::fs::remove_file(&path);
+                        }
+                        Err(err) => warn!("failed to record crash artifact {path:?}: {err:#}"),
+                    },
+                    None => warn!("worker {worker_id} crashed ({status:?}), no artifact found"),
+                }
+            }
+        }
+
+        if let Err(err) = crash_store.update_stats(&stats.lock().unwrap().clone()) {
+            warn!("failed to persist crash manifest for worker {worker_id}: {err:#}");
+        }
+
+        if persistent == 0 {
+            break;
+        }
+
+        tokio::select! {
+            _ = wait_for_shutdown(&mut shutdown) => {
+                info!("worker {worker_id} shutting down, flushing final stats");
+                break;
+            }
+            _ = sleep(backoff) => {}
+        }
+        backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
     }
 
-    /// 运行单个 fuzzer 进程
-    async fn run_fuzzer(&self, worker_id: usize) -> Result<()> {
-        println!("outcome: {:?}", self.runtime_stats.execs_sec);
-        let mut running = self.fuzz_cmd(self.runtime_stats.cmd.as_str()).await;
+    Ok(())
+}
 
-        println!("child is: {:?}", running);
+/// 等待 shutdown 信号：`watch` 保存的是最新值而不是一次性的唤醒，所以先检查有没有
+/// 错过（已经是 `true`），再去等下一次变化，这样不会因为没有正好 await 在这里而漏掉停止信号
+async fn wait_for_shutdown(shutdown: &mut watch::Receiver<bool>) {
+    if *shutdown.borrow() {
+        return;
+    }
+    let _ = shutdown.changed().await;
+}
 
-        // 实现具体的 fuzzer 运行逻辑
-        Ok(())
+/// 运行单个 fuzzer 进程，语料目录为该 worker 独占的 `work_dir`
+///
+/// 进程最多运行 `timeout_secs`（见 `ServerConfig`），超时会被当成挂起：杀掉子进程并返回
+/// `FuzzOutcome::TimedOut`，不做 crash artifact 查找，也不计入 `crashes`。
+async fn run_fuzzer(stats: Arc<std::sync::Mutex<RuntimeStats>>, work_dir: &Path) -> Result<FuzzOutcome> {
+    let worker_id = stats.lock().unwrap().worker_id;
+    let cmd = stats.lock().unwrap().cmd.clone();
+    println!("worker {worker_id} running command: {:?} (corpus: {work_dir:?})", &cmd);
+
+    let mut child = fuzz_cmd(cmd.as_str(), work_dir).await?;
+
+    let stdout = child.stdout.take().with_context(|| "child has no stdout")?;
+    let stderr = child.stderr.take().with_context(|| "child has no stderr")?;
+
+    let stdout_stats = stats.clone();
+    let stdout_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut stats = stdout_stats.lock().unwrap();
+            parse_libfuzzer_line(&line, &mut stats);
+        }
+    });
+
+    let stderr_stats = stats.clone();
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut stats = stderr_stats.lock().unwrap();
+            parse_libfuzzer_line(&line, &mut stats);
+        }
+    });
+
+    let report_task = tokio::spawn(crate::report::run(stats.clone()));
+
+    let timeout_secs = SERVER_CONFIG.get().unwrap().timeout_secs;
+    let status = match timeout(Duration::from_secs(timeout_secs), child.wait()).await {
+        Ok(status) => status.with_context(|| "libfuzzer child errored out")?,
+        Err(_) => {
+            warn!("worker {worker_id} timed out after {timeout_secs}s, killing it");
+            child.kill().await.with_context(|| "failed to kill timed-out libfuzzer child")?;
+            child.wait().await.with_context(|| "failed to reap timed-out libfuzzer child")?;
+            let _ = tokio::join!(stdout_task, stderr_task);
+            report_task.abort();
+            // 是我们自己杀的，不是 fuzzer 崩了：跳过下面的 crash 分类，直接当超时处理。
+            return Ok(FuzzOutcome::TimedOut);
+        }
+    };
+    let _ = tokio::join!(stdout_task, stderr_task);
+    report_task.abort();
+
+    println!("worker {worker_id} exited with: {status:?}");
+
+    if status.success() {
+        return Ok(FuzzOutcome::Clean);
+    }
+
+    let artifact = find_crash_artifact(work_dir).unwrap_or_else(|err| {
+        warn!("failed to look for crash artifact: {err:#}");
+        None
+    });
+
+    Ok(FuzzOutcome::Crash { status, artifact })
+}
+
+async fn fuzz_cmd(cmd: &str, work_dir: &Path) -> Result<Child> {
+    println!("Running command: {:?}", &cmd);
+
+    let child = Command::new(cmd)
+        .arg(work_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // shutdown 时 `start_fuzzer_monitor` 的 select! 会直接 break，丢弃还在运行的
+        // `run_fuzzer` future（以及它持有的这个 `Child`）；没有这个标记子进程就会被遗孤。
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format_err!("libfuzzer failed to start."))?;
+
+    Ok(child)
+}
+
+/// 按 worker 的 `RuntimeStats` 汇总成一份合并视图：计数类字段求和，覆盖率类字段取最大值。
+/// 仅用于 `run_fuzzers` 结束时打一条汇总日志，不会上报给调度端——每个 worker 自己的
+/// `report::run` 任务已经在实时上报了，调度端按 `worker_id` 收齐后求和即可。
+fn aggregate_stats(workers: &[Arc<std::sync::Mutex<RuntimeStats>>]) -> RuntimeStats {
+    let mut combined = RuntimeStats::default();
+    for worker in workers {
+        let stats = worker.lock().unwrap();
+        combined.count += stats.count;
+        combined.crashes += stats.crashes;
+        combined.queuees += stats.queuees;
+        combined.execs_sec += stats.execs_sec;
+        combined.basicblocks = combined.basicblocks.max(stats.basicblocks);
+        combined.whole_basicblocks = combined.whole_basicblocks.max(stats.whole_basicblocks);
+        combined.functions = combined.functions.max(stats.functions);
+        combined.whole_functions = combined.whole_functions.max(stats.whole_functions);
+        combined.lines = combined.lines.max(stats.lines);
+        combined.whole_lines = combined.whole_lines.max(stats.whole_lines);
+        combined.edges = combined.edges.max(stats.edges);
+        combined.whole_edges = combined.whole_edges.max(stats.whole_edges);
     }
+    combined
+}
+
+/// 语料同步的轮询间隔
+const CORPUS_SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 周期性地在 `corpus_root` 下各 worker 的语料目录之间互相补齐对方没有的测试用例，
+/// 让并行 worker 像多核 libFuzzer 那样共享彼此发现的进展
+async fn sync_corpora(corpus_root: PathBuf, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        if *shutdown.borrow() {
+            break;
+        }
+
+        tokio::select! {
+            _ = wait_for_shutdown(&mut shutdown) => break,
+            _ = sleep(CORPUS_SYNC_INTERVAL) => {}
+        }
+
+        let Ok(entries) = std::fs::read_dir(&corpus_root) else { continue };
+        let worker_dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+
+        let mut known: HashMap<OsString, PathBuf> = HashMap::new();
+        for dir in &worker_dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else { continue };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let name = entry.file_name();
+                // crash/oom/timeout 产物只属于产生它们的 worker，`CrashStore` 会负责归档、
+                // 去重；同步给别的 worker 既污染了语料目录，也会被那个 worker 的
+                // `find_crash_artifact` 误认成自己的崩溃。
+                let is_crash_artifact = name
+                    .to_str()
+                    .map(|name| CRASH_ARTIFACT_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+                    .unwrap_or(false);
+                if is_crash_artifact {
+                    continue;
+                }
+                known.entry(name).or_insert_with(|| entry.path());
+            }
+        }
+
+        for dir in &worker_dirs {
+            for (name, src) in &known {
+                let dest = dir.join(name);
+                if !dest.exists() {
+                    if let Err(err) = std::fs::copy(src, &dest) {
+                        debug!("failed to sync corpus entry {src:?} to {dest:?}: {err}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// libFuzzer 在崩溃时于语料目录留下的产物文件名前缀
+const CRASH_ARTIFACT_PREFIXES: [&str; 3] = ["crash-", "oom-", "timeout-"];
+
+/// 在 worker 的语料目录里寻找 libFuzzer 写下的崩溃产物。实际的去重、归档交给 `CrashStore`，
+/// 这里只负责定位文件
+fn find_crash_artifact(work_dir: &Path) -> Result<Option<PathBuf>> {
+    let found = std::fs::read_dir(work_dir)
+        .with_context(|| format!("failed to list {work_dir:?} for crash artifacts"))?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .map(|name| CRASH_ARTIFACT_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.path());
 
-    async fn fuzz_cmd(&self, cmd: &str) -> Result<Child> {
-        println!("Running command: {:?}", &cmd);
+    Ok(found)
+}
 
-        let child = Command::new(cmd)
-            .spawn()
-            .with_context(|| format_err!("libfuzzer failed to start."))?;
+/// 解析一行 libFuzzer 输出，更新 `stats`
+///
+/// 典型进度行形如：
+/// `#12345 NEW cov: 560 ft: 781 corp: 42/1337b lim: 4096 exec/s: 9001 rss: 120Mb`
+///
+/// 崩溃计数不在这里做：`==ERROR: libFuzzer:`/`SUMMARY: ...Sanitizer` 这类标记行和进程的
+/// 非零退出状态往往同时出现，`stats.crashes` 由 `start_fuzzer_monitor` 根据
+/// `FuzzOutcome::Crash` 统一计数一次，避免这里再加一次重复计数。
+fn parse_libfuzzer_line(line: &str, stats: &mut RuntimeStats) {
+    if !line.starts_with('#') {
+        return;
+    }
 
-        Ok(child)
+    for token in line.split_whitespace() {
+        if let Some(n) = token.strip_prefix('#') {
+            if let Ok(v) = n.parse::<u64>() {
+                stats.count = v;
+            }
+        }
     }
 
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            "cov:" => {
+                if let Some(v) = tokens.get(i + 1).and_then(|s| s.parse::<u64>().ok()) {
+                    stats.edges = v;
+                }
+            }
+            "ft:" => {
+                if let Some(v) = tokens.get(i + 1).and_then(|s| s.parse::<u64>().ok()) {
+                    stats.basicblocks = v;
+                }
+            }
+            "corp:" => {
+                if let Some(corp) = tokens.get(i + 1) {
+                    if let Some((count, _size)) = corp.split_once('/') {
+                        if let Ok(v) = count.parse::<u64>() {
+                            stats.queuees = v;
+                        }
+                    }
+                }
+            }
+            "exec/s:" => {
+                if let Some(v) = tokens.get(i + 1).and_then(|s| s.parse::<f64>().ok()) {
+                    stats.execs_sec = v;
+                }
+            }
+            _ => {}
+        }
+    }
 }
\ No newline at end of file
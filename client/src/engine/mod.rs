@@ -0,0 +1,46 @@
+use crate::parse::CommandLine;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+pub mod libfuzzer_c;
+
+/// 一个 fuzzing 后端：语言 + 引擎的一种组合对应一个实现。新增 Go/Java/Rust/C# 等
+/// libFuzzer 后端（或 AFL 这样的引擎）只需要实现这个 trait 并加入 `registered_engines`，
+/// 不用再去改 `select_engine` 里的中心化 match。
+#[async_trait]
+pub trait FuzzEngine: Send + Sync {
+    /// 运行这个后端的 fuzzer，是否持续运行由 `SERVER_CONFIG.persistent` 决定（命令行的 `-p` 会覆盖配置文件里的值）
+    async fn run_fuzzer(&self, opt: &CommandLine) -> Result<()>;
+
+    /// 该引擎是否支持给定的语言/引擎组合（语言大小写不敏感）
+    fn supports(language: &str, engine: &str) -> bool
+    where
+        Self: Sized;
+}
+
+/// 已注册的 fuzzing 引擎，每一项都是 `(展示名, supports 函数指针, 构造函数)`。
+///
+/// 新增后端只需要在这里加一行：`lookup` 不再直接比较 `(language, engine)` 字符串，
+/// 而是把匹配逻辑委托给各引擎自己的 `FuzzEngine::supports`。
+fn registered_engines() -> Vec<(&'static str, fn(&str, &str) -> bool, fn() -> Box<dyn FuzzEngine>)> {
+    vec![(
+        "c/xlibfuzzer",
+        libfuzzer_c::LibFuzzerEngine::supports,
+        || Box::new(libfuzzer_c::LibFuzzerEngine) as Box<dyn FuzzEngine>,
+    )]
+}
+
+/// 按 `(language, engine)` 在注册表里查找对应的后端，通过各引擎的 `supports` 判断是否匹配；
+/// 找不到时返回的错误里会列出当前所有已注册的组合，方便排查是拼错了还是确实没实现
+pub fn lookup(language: &str, engine: &str) -> Result<Box<dyn FuzzEngine>> {
+    let engines = registered_engines();
+
+    if let Some((_, _, make)) = engines.iter().find(|(_, supports, _)| supports(language, engine)) {
+        return Ok(make());
+    }
+
+    let available = engines.iter().map(|(name, _, _)| *name).collect::<Vec<_>>().join(", ");
+    Err(anyhow!(
+        "Unsupported language: {language} or engine: {engine}. Registered engines: {available}"
+    ))
+}
@@ -2,6 +2,8 @@
 pub mod parse;
 pub mod config;
 pub mod schedule;
+pub mod report;
+pub mod crash;
 
 pub mod engine;
 
@@ -1,41 +1,84 @@
 use clap::Parser;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-// use std::path::PathBuf;
+use std::path::PathBuf;
 
 // CommandLine结构体，derive属性（宏）提供了Parser和Debug特性
+//
+// `address`/`language`/`engine`/`args` 不设 `default_value`，而是留成 `None`：
+// 这样 `config::load_layered_config` 才能分辨出“用户确实传了这个 flag”和
+// “用户没传，请用 settings/*.toml 里的值”，否则 CLI 层会用 clap 的默认值
+// 把配置文件里设置的值覆盖掉。
 #[derive(Parser, Debug)]
 pub struct CommandLine {
-    /// 服务绑定的IP地址及端口号
-    #[arg(short = 's', long = "address", value_parser = parse_ipaddr, default_value = "")]
-    pub address: SocketAddr,
+    /// 调度端地址，支持 `IP`、`IP:port`、`unix:/path`（UDS）、`unix:@name`（抽象命名空间）
+    #[arg(short = 's', long = "address", value_parser = parse_address)]
+    pub address: Option<ServerAddress>,
     /// 类库的fuzz语言
-    #[arg(short = 'l', long = "fuzzing language", default_value = "C")]
-    pub language: String,
+    #[arg(short = 'l', long = "fuzzing language")]
+    pub language: Option<String>,
     /// 类库的引擎
-    #[arg(short = 'e', long = "fuzzing engine", default_value = "xlibfuzzer")]
-    pub engine: String,
+    #[arg(short = 'e', long = "fuzzing engine")]
+    pub engine: Option<String>,
     /// 持续fuzz
     #[arg(short = 'p', long = "persistent", action = clap::ArgAction::Count)]
     pub persistent: u8,
     /// fuzzer的执行命令
-    #[arg(short = 'a', long = "args", default_value = "Fuzzer [args]")]
-    pub args: String,
+    #[arg(short = 'a', long = "args")]
+    pub args: Option<String>,
 }
 
-/// 解析地址，支持IP及IP:port格式，默认port为3000
-fn parse_ipaddr(s: &str) -> Result<SocketAddr, String> {
+/// 调度端地址，既可以是普通的 TCP socket，也可以是本地的 Unix domain socket
+///
+/// Unix socket 又分两种：落在文件系统上的路径，和 Linux 独有的、不占用文件系统路径的
+/// 抽象命名空间（abstract namespace，名字前面是一个 `\0` 字节）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerAddress {
+    Tcp(SocketAddr),
+    /// 文件系统路径形式的 Unix domain socket，如 `unix:/run/xfl/scheduler.sock`
+    Unix(PathBuf),
+    /// Linux 抽象命名空间 socket，如 `unix:@xfl-scheduler`
+    UnixAbstract(String),
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerAddress::Tcp(addr) => write!(f, "{addr}"),
+            ServerAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+            ServerAddress::UnixAbstract(name) => write!(f, "unix:@{name}"),
+        }
+    }
+}
+
+impl Default for ServerAddress {
+    fn default() -> Self {
+        ServerAddress::Tcp(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))
+    }
+}
+
+/// 解析地址，支持IP及IP:port格式（默认port为3000），以及 `unix:/path`/`unix:@name` 格式
+pub(crate) fn parse_address(s: &str) -> Result<ServerAddress, String> {
     // 如果输入字符串为空,则返回0.0.0.0:0
     if s.is_empty() {
-        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0));
+        return Ok(ServerAddress::default());
+    }
+
+    if let Some(rest) = s.strip_prefix("unix:") {
+        return if let Some(name) = rest.strip_prefix('@') {
+            Ok(ServerAddress::UnixAbstract(name.to_string()))
+        } else {
+            Ok(ServerAddress::Unix(PathBuf::from(rest)))
+        };
     }
 
     // 尝试解析SocketAddr类型
     match s.parse::<SocketAddr>() {
-        Ok(ip) => Ok(ip),
+        Ok(ip) => Ok(ServerAddress::Tcp(ip)),
         // 尝试解析IpAddr类型
         Err(_) => match s.parse::<IpAddr>() {
-            Ok(ip_addr) => Ok(SocketAddr::new(ip_addr, 3000)),
-            Err(_) => Err(format!("非法IP及端口: {}", s)),
+            Ok(ip_addr) => Ok(ServerAddress::Tcp(SocketAddr::new(ip_addr, 3000))),
+            Err(_) => Err(format!("非法地址: {}", s)),
         },
     }
 }
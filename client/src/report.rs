@@ -0,0 +1,95 @@
+use crate::config::SERVER_CONFIG;
+use crate::engine::libfuzzer_c::RuntimeStats;
+use crate::grpc::scheduler_client::SchedulerClient;
+use crate::parse::ServerAddress;
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use std::sync::{Arc, Mutex};
+use tonic::transport::{Channel, Endpoint, Uri};
+use tower::service_fn;
+
+use tokio::time::{sleep, Duration};
+
+/// 初次重连等待时间，随后按指数退避增长
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// 重连等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 周期性地把 `stats` 上报给 `SERVER_CONFIG.server_addr` 指向的调度端
+///
+/// 调度端连不上或连接中断时按指数退避重连，不会让上报失败拖垮整个 fuzzing 任务。
+pub async fn run(stats: Arc<Mutex<RuntimeStats>>) {
+    let server_addr = SERVER_CONFIG.get().unwrap().server_addr.clone();
+    let interval = Duration::from_secs(SERVER_CONFIG.get().unwrap().report_interval_secs.max(1));
+
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let mut client = match connect(&server_addr).await {
+            Ok(client) => {
+                backoff = INITIAL_BACKOFF;
+                client
+            }
+            Err(err) => {
+                warn!("failed to connect to scheduler at {server_addr}: {err:#}, retrying in {backoff:?}");
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+
+        loop {
+            sleep(interval).await;
+
+            let report = stats.lock().unwrap().to_report();
+            if let Err(err) = client.report_stats(report).await {
+                warn!("lost connection to scheduler: {err}, reconnecting");
+                break;
+            }
+            debug!("reported stats to scheduler at {server_addr}");
+        }
+    }
+}
+
+/// 按地址类型选择合适的传输方式拨号：TCP 走普通的 HTTP/2 连接，
+/// Unix socket（含抽象命名空间）则通过自定义连接器复用同一个 tonic Channel
+async fn connect(addr: &ServerAddress) -> Result<SchedulerClient<Channel>> {
+    match addr {
+        ServerAddress::Tcp(socket_addr) => {
+            let channel = Endpoint::from_shared(format!("http://{socket_addr}"))
+                .with_context(|| format!("invalid scheduler endpoint {socket_addr}"))?
+                .connect()
+                .await
+                .with_context(|| format!("failed to connect to {socket_addr}"))?;
+            Ok(SchedulerClient::new(channel))
+        }
+        ServerAddress::Unix(path) => {
+            let path = path.clone();
+            let channel = Endpoint::from_static("http://[::]:0")
+                .connect_with_connector(service_fn(move |_: Uri| {
+                    let path = path.clone();
+                    async move { tokio::net::UnixStream::connect(path).await }
+                }))
+                .await
+                .with_context(|| "failed to connect to unix domain socket")?;
+            Ok(SchedulerClient::new(channel))
+        }
+        ServerAddress::UnixAbstract(name) => {
+            let name = name.clone();
+            let channel = Endpoint::from_static("http://[::]:0")
+                .connect_with_connector(service_fn(move |_: Uri| {
+                    let name = name.clone();
+                    async move {
+                        let addr = std::os::linux::net::SocketAddrExt::from_abstract_name(name.as_bytes())?;
+                        let std_stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+                        std_stream.set_nonblocking(true)?;
+                        tokio::net::UnixStream::from_std(std_stream)
+                    }
+                }))
+                .await
+                .with_context(|| "failed to connect to abstract unix domain socket")?;
+            Ok(SchedulerClient::new(channel))
+        }
+    }
+}
@@ -1,23 +1,20 @@
+use crate::config::SERVER_CONFIG;
 use crate::parse::CommandLine;
-use crate::engine::libfuzzer_c;
-use crate::engine::libfuzzer_csharp;
-use crate::engine::libfuzzer_go;
-use crate::engine::libfuzzer_java;
-use crate::engine::libfuzzer_rust;
+use crate::engine;
 
+use anyhow::Result;
+use log::error;
 
-/// 根据不同的语言来选择不同的引擎
-pub async fn select_engine(opt: &CommandLine) {
-    // 语言支持大小写混写
-    match (opt.language.to_lowercase().as_str(), opt.engine.as_str()) {
-        ("c", "xlibfuzzer") => libfuzzer_c::run_fuzzer(opt).await,
-        // ("c#", "xlibfuzzer") => libfuzzer_csharp::run_fuzzer(opt).await,
-        // ("go", "xlibfuzzer") => libfuzzer_go::run_fuzzer(opt).await,
-        // ("java", "xlibfuzzer") => libfuzzer_java::run_fuzzer(opt).await,
-        // ("rust", "xlibfuzzer") => libfuzzer_rust::run_fuzzer(opt).await,
-        _ => {
-            println!("Unsupported language: {} or engine: {}", opt.language, opt.engine);
-            return;
-        }
+/// 根据语言 + 引擎从注册表里选出对应的后端并运行
+///
+/// 语言/引擎取自 `SERVER_CONFIG`（已经叠加了 settings/*.toml 与 CLI 覆盖），而不是
+/// 直接读 `opt`，因为 `opt.language`/`opt.engine` 在命令行没传时是 `None`。
+pub async fn select_engine(opt: &CommandLine) -> Result<()> {
+    let config = SERVER_CONFIG.get().expect("config must be initialized before select_engine");
+    let fuzz_engine = engine::lookup(&config.language, &config.engine)?;
+    if let Err(err) = fuzz_engine.run_fuzzer(opt).await {
+        error!("fuzzer exited with error: {err:#}");
+        return Err(err);
     }
+    Ok(())
 }